@@ -1,7 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_cargo::style::CLAP_STYLING;
+use guppy::graph::{DependencyDirection, PackageGraph, PackageQuery, PackageSet};
 use guppy::MetadataCommand;
-use guppy::graph::{DependencyDirection, PackageGraph, PackageSet};
+use serde::Serialize;
 use std::collections::HashSet;
 
 #[derive(Parser)]
@@ -38,17 +39,88 @@ struct TopoArgs {
     #[arg(short, long, default_value = "false")]
     all: bool,
 
-    /// Output compact line-separated list of crate names only
+    /// Output compact line-separated list of crate names only.
+    /// Shorthand for `--format compact`.
     #[arg(short, long)]
     compact: bool,
 
+    /// Output format: human-readable text, a compact name list, machine-readable JSON,
+    /// or a Graphviz DOT graph
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
     /// Select a specific package as the root of the dependency tree
     #[arg(short = 'p', long = "package")]
     package: Option<String>,
-    
+
+    /// With --package, list packages that (transitively) depend on it instead of
+    /// packages it depends on: "if I change this crate, what else must rebuild?"
+    #[arg(long, requires = "package")]
+    dependents: bool,
+
     /// Exclude specific workspace members from the output
     #[arg(long)]
     exclude: Vec<String>,
+
+    /// Exit with a non-zero status (and print each cycle's trace) if the
+    /// resolved dependency graph contains a cycle
+    #[arg(long)]
+    deny_cycles: bool,
+
+    /// Group the topological order into parallel build "waves" (generations):
+    /// every package in wave N depends only on packages in waves < N
+    #[arg(long)]
+    waves: bool,
+
+    /// With --waves, run this command per package within each wave, substituting
+    /// `{}` with the package name (e.g. "cargo build -p {}")
+    #[arg(long, requires = "waves")]
+    exec: Option<String>,
+
+    /// Only consider dependencies enabled for this target triple (e.g. `x86_64-pc-windows-msvc`)
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Feature names to enable when resolving the dependency graph
+    #[arg(long, value_delimiter = ',')]
+    features: Vec<String>,
+
+    /// Resolve with no default features enabled
+    #[arg(long)]
+    no_default_features: bool,
+
+    /// Resolve with every feature of the root packages (the workspace members, or the
+    /// `--package` root) enabled
+    #[arg(long, conflicts_with_all = ["features", "no_default_features"])]
+    all_features: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Emoji-decorated human-readable listing (the default)
+    Text,
+    /// Newline-separated crate names only
+    Compact,
+    /// Machine-readable JSON suitable for CI scripts and other tooling
+    Json,
+    /// Graphviz DOT digraph of the resolved dependency graph
+    Dot,
+}
+
+/// A single package entry in the `--format json` output.
+#[derive(Serialize)]
+struct PackageJson {
+    name: String,
+    version: String,
+    is_workspace_member: bool,
+    dependencies: Vec<String>,
+}
+
+/// Top-level schema emitted by `--format json`.
+#[derive(Serialize)]
+struct TopoJson {
+    order: &'static str,
+    packages: Vec<PackageJson>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -80,34 +152,99 @@ fn run_topo_command(args: TopoArgs) -> Result<(), Box<dyn std::error::Error>> {
         })
         .collect();
 
-    // Determine the dependency set based on root package selection
-    let dependency_set = if let Some(root_package) = &args.package {
+    // Determine the root query (workspace, or a single `--package`) based on root package
+    // selection. Kept around (not just resolved) so `narrow_by_platform_and_features` can
+    // rebuild a feature-level query over the same roots instead of the already-expanded
+    // dependency set.
+    let query = if let Some(root_package) = &args.package {
         // Find the root package by name
         let root_pkg = package_graph
             .packages()
             .find(|pkg| pkg.name() == root_package)
             .ok_or_else(|| format!("Package '{}' not found in workspace", root_package))?;
 
-        // Query dependencies starting from root package, filtering dev-only deps
-        package_graph.query_directed(
-            std::iter::once(root_pkg.id()), 
+        // Query dependencies (or, with --dependents, reverse dependents) starting
+        // from the root package
+        let query_direction = if args.dependents {
+            DependencyDirection::Reverse
+        } else {
             DependencyDirection::Forward
-        )?.resolve_with_fn(|_query, link| {
-            // Include the link unless it's dev-only and we're not including dev deps
+        };
+        package_graph.query_directed(std::iter::once(root_pkg.id()), query_direction)?
+    } else {
+        // Query full workspace
+        package_graph.query_workspace()
+    };
+
+    let dependency_set = if args.package.is_some() {
+        // Filter out dev-only deps unless we're including dev deps
+        query.clone().resolve_with_fn(|_query, link| {
             !link.dev_only() || args.include_dev
         })
     } else {
-        // Query full workspace
-        package_graph.query_workspace().resolve()
+        query.clone().resolve()
     };
 
-    if args.compact {
+    let dependency_set =
+        narrow_by_platform_and_features(&package_graph, dependency_set, &query, &args)?;
+
+    if args.deny_cycles {
+        let cycles = find_cycles(&package_graph, &dependency_set, args.include_dev);
+        if !cycles.is_empty() {
+            println!("Dependency cycles detected:\n");
+            for cycle in &cycles {
+                println!("  {}", cycle.join(" → "));
+            }
+            return Err("dependency graph contains cycles".into());
+        }
+    }
+
+    if args.waves {
+        return show_waves(
+            &package_graph,
+            &dependency_set,
+            &workspace_ids,
+            args.all,
+            args.exec.as_deref(),
+        );
+    }
+
+    // `--compact` is kept as a shorthand for `--format compact`.
+    let format = args.format.unwrap_or(if args.compact {
+        OutputFormat::Compact
+    } else {
+        OutputFormat::Text
+    });
+
+    if format == OutputFormat::Json {
+        show_json_output(&dependency_set, &workspace_ids, args.reverse, args.all)?;
+    } else if format == OutputFormat::Dot {
+        show_dot_output(
+            &package_graph,
+            &dependency_set,
+            &workspace_ids,
+            args.all,
+            args.include_dev,
+        )?;
+    } else if format == OutputFormat::Compact {
         show_compact_output(&dependency_set, &workspace_ids, args.reverse, args.all)?;
     } else {
         if args.package.is_some() {
             let root_name = args.package.as_ref().unwrap();
-            if args.reverse {
-                println!("Dependencies from '{}' in reverse topological order:", root_name);
+            if args.dependents {
+                if args.reverse {
+                    println!(
+                        "Dependents of '{}' in reverse topological order:",
+                        root_name
+                    );
+                } else {
+                    println!("Dependents of '{}' in topological order:", root_name);
+                }
+            } else if args.reverse {
+                println!(
+                    "Dependencies from '{}' in reverse topological order:",
+                    root_name
+                );
             } else {
                 println!("Dependencies from '{}' in topological order:", root_name);
             }
@@ -122,13 +259,29 @@ fn run_topo_command(args: TopoArgs) -> Result<(), Box<dyn std::error::Error>> {
             println!("Excluding: {}", args.exclude.join(", "));
         }
         println!();
-        
+
+        let highlight = if args.dependents {
+            args.package.as_deref()
+        } else {
+            None
+        };
+
         if args.all {
-            show_all_dependencies_topological_order(&dependency_set, &workspace_ids, args.reverse)?;
+            show_all_dependencies_topological_order(
+                &dependency_set,
+                &workspace_ids,
+                args.reverse,
+                highlight,
+            )?;
         } else {
-            show_workspace_topological_order(&dependency_set, &workspace_ids, args.reverse)?;
+            show_workspace_topological_order(
+                &dependency_set,
+                &workspace_ids,
+                args.reverse,
+                highlight,
+            )?;
         }
-        
+
         if args.include_dev {
             println!("\nDev-dependencies analysis:");
             show_dev_dependencies(&package_graph, &dependency_set, &workspace_ids)?;
@@ -142,6 +295,7 @@ fn show_workspace_topological_order(
     dependency_set: &PackageSet,
     workspace_ids: &HashSet<&guppy::PackageId>,
     reverse: bool,
+    highlight: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // in guppy DependencyDirection, the logic is reversed from traditional topological order
     let direction = if reverse {
@@ -160,7 +314,7 @@ fn show_workspace_topological_order(
                 .direct_links()
                 .filter(|link| !link.dev_only()) // Exclude dev dependencies
                 .filter(|link| workspace_ids.contains(&link.to().id()))
-                .map(|link| link.to().name())
+                .map(|link| mark_if_highlighted(link.to().name(), highlight))
                 .collect();
 
             if !deps.is_empty() {
@@ -177,6 +331,7 @@ fn show_all_dependencies_topological_order(
     dependency_set: &PackageSet,
     workspace_ids: &HashSet<&guppy::PackageId>,
     reverse: bool,
+    highlight: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // in guppy DependencyDirection, the logic is reversed from traditional topological order
     let direction = if reverse {
@@ -200,7 +355,11 @@ fn show_all_dependencies_topological_order(
                     let to_package = link.to();
                     let is_workspace_dep = workspace_ids.contains(&to_package.id());
                     let marker = if is_workspace_dep { "📦" } else { "📄" };
-                    format!("{} {}", marker, to_package.name())
+                    format!(
+                        "{} {}",
+                        marker,
+                        mark_if_highlighted(to_package.name(), highlight)
+                    )
                 })
                 .collect();
 
@@ -214,6 +373,16 @@ fn show_all_dependencies_topological_order(
     Ok(())
 }
 
+/// Marks `name` with a ⭐ when it matches the `--dependents` target package,
+/// so a direct link toward the query target stands out in the listing.
+fn mark_if_highlighted(name: &str, highlight: Option<&str>) -> String {
+    if highlight == Some(name) {
+        format!("⭐{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
 fn show_dev_dependencies(
     package_graph: &PackageGraph,
     dependency_set: &PackageSet,
@@ -281,6 +450,490 @@ fn show_compact_output(
     Ok(())
 }
 
+fn show_json_output(
+    dependency_set: &PackageSet,
+    workspace_ids: &HashSet<&guppy::PackageId>,
+    reverse: bool,
+    all: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // in guppy DependencyDirection, the logic is reversed from traditional topological order
+    let direction = if reverse {
+        DependencyDirection::Forward // dependent packages appear before their dependencies
+    } else {
+        DependencyDirection::Reverse // dependencies appear first by default
+    };
+
+    let mut packages = Vec::new();
+
+    for package in dependency_set.packages(direction) {
+        let is_workspace = workspace_ids.contains(&package.id());
+
+        if !all && !is_workspace {
+            continue;
+        }
+
+        let dependencies: Vec<String> = package
+            .direct_links()
+            .filter(|link| !link.dev_only())
+            .filter(|link| all || workspace_ids.contains(&link.to().id()))
+            .map(|link| link.to().name().to_string())
+            .collect();
+
+        packages.push(PackageJson {
+            name: package.name().to_string(),
+            version: package.version().to_string(),
+            is_workspace_member: is_workspace,
+            dependencies,
+        });
+    }
+
+    let output = TopoJson {
+        order: if reverse { "reverse" } else { "forward" },
+        packages,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Visitor used to render the dependency graph as Graphviz DOT via guppy's
+/// own `PackageSet::display_dot`, rather than hand-walking `direct_links()`.
+/// The visitor only writes each node's and edge's label text. Workspace members
+/// and external crates are told apart by the 📦/📄 marker in the node label (the
+/// same markers the text output uses); no node shape or colour is set. Dev-only
+/// links are still drawn as edges; unless `include_dev` is set, their label is
+/// left empty.
+struct TopoDotVisitor<'a> {
+    workspace_ids: &'a HashSet<&'a guppy::PackageId>,
+    include_dev: bool,
+}
+
+impl guppy::graph::PackageDotVisitor for TopoDotVisitor<'_> {
+    fn visit_package(
+        &self,
+        package: guppy::graph::PackageMetadata<'_>,
+        f: &mut guppy::graph::DotWrite<'_, '_>,
+    ) -> std::fmt::Result {
+        let marker = if self.workspace_ids.contains(&package.id()) {
+            "📦"
+        } else {
+            "📄"
+        };
+        write!(f, "{} {} v{}", marker, package.name(), package.version())
+    }
+
+    fn visit_link(
+        &self,
+        link: guppy::graph::PackageLink<'_>,
+        f: &mut guppy::graph::DotWrite<'_, '_>,
+    ) -> std::fmt::Result {
+        if link.dev_only() && !self.include_dev {
+            return Ok(());
+        }
+        write!(f, "{} -> {}", link.from().name(), link.to().name())
+    }
+}
+
+/// Renders the resolved `dependency_set` as a complete Graphviz DOT digraph using
+/// `PackageSet::display_dot`, restricted to workspace members unless `all` is set.
+/// `display_dot` already emits the full `digraph { ... }` block, braces and all, so
+/// the returned string is ready to print (or pipe to `dot -Tsvg`) verbatim.
+fn render_dot_body(
+    package_graph: &PackageGraph,
+    dependency_set: &PackageSet,
+    workspace_ids: &HashSet<&guppy::PackageId>,
+    all: bool,
+    include_dev: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let scoped_set = if all {
+        dependency_set.clone()
+    } else {
+        let workspace_only_ids: Vec<&guppy::PackageId> = dependency_set
+            .packages(DependencyDirection::Forward)
+            .filter(|package| workspace_ids.contains(&package.id()))
+            .map(|package| package.id())
+            .collect();
+        package_graph.resolve_ids(workspace_only_ids)?
+    };
+
+    // Bind to a local before `scoped_set` drops: `display_dot` returns a value
+    // borrowing from it, so it can't be turned into an owned `String` in a tail
+    // expression without outliving the borrow.
+    let rendered = scoped_set
+        .display_dot(TopoDotVisitor {
+            workspace_ids,
+            include_dev,
+        })
+        .to_string();
+    Ok(rendered)
+}
+
+fn show_dot_output(
+    package_graph: &PackageGraph,
+    dependency_set: &PackageSet,
+    workspace_ids: &HashSet<&guppy::PackageId>,
+    all: bool,
+    include_dev: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = render_dot_body(
+        package_graph,
+        dependency_set,
+        workspace_ids,
+        all,
+        include_dev,
+    )?;
+    println!("{}", body);
+
+    Ok(())
+}
+
+/// Detects cycles in the resolved `dependency_set` using Tarjan's
+/// strongly-connected-component algorithm, then reconstructs one
+/// representative cycle per non-trivial component (and per self-loop) as an
+/// ordered trace, e.g. `a → b → c → a`.
+fn find_cycles(
+    package_graph: &PackageGraph,
+    dependency_set: &PackageSet,
+    include_dev: bool,
+) -> Vec<Vec<String>> {
+    use guppy::PackageId;
+
+    let mut adjacency: std::collections::HashMap<&PackageId, Vec<&PackageId>> =
+        std::collections::HashMap::new();
+
+    for package in dependency_set.packages(DependencyDirection::Forward) {
+        let neighbors = package
+            .direct_links()
+            .filter(|link| !link.dev_only() || include_dev)
+            .filter(|link| dependency_set.contains(link.to().id()).unwrap_or(false))
+            .map(|link| link.to().id())
+            .collect();
+        adjacency.insert(package.id(), neighbors);
+    }
+
+    let sccs = tarjan_scc(&adjacency);
+    let name_of = |id: &PackageId| package_graph.metadata(id).unwrap().name().to_string();
+
+    let mut cycles = Vec::new();
+    for component in &sccs {
+        if component.len() > 1 {
+            if let Some(cycle) = reconstruct_cycle(&adjacency, component) {
+                cycles.push(cycle.iter().map(|id| name_of(id)).collect());
+            }
+        } else if let Some(&node) = component.first() {
+            // A single-node "component" is only a cycle if it has a self-loop.
+            if adjacency
+                .get(node)
+                .is_some_and(|succs| succs.contains(&node))
+            {
+                cycles.push(vec![name_of(node), name_of(node)]);
+            }
+        }
+    }
+
+    cycles
+}
+
+/// Tarjan's strongly-connected-components algorithm over a plain adjacency map.
+/// Generic over the node type so it can be unit-tested with plain values
+/// instead of requiring a real `PackageGraph`.
+fn tarjan_scc<T: Eq + std::hash::Hash + Copy>(
+    adjacency: &std::collections::HashMap<T, Vec<T>>,
+) -> Vec<Vec<T>> {
+    struct State<T> {
+        index: std::collections::HashMap<T, usize>,
+        low_link: std::collections::HashMap<T, usize>,
+        on_stack: std::collections::HashSet<T>,
+        stack: Vec<T>,
+        next_index: usize,
+        sccs: Vec<Vec<T>>,
+    }
+
+    fn strong_connect<T: Eq + std::hash::Hash + Copy>(
+        node: T,
+        adjacency: &std::collections::HashMap<T, Vec<T>>,
+        state: &mut State<T>,
+    ) {
+        state.index.insert(node, state.next_index);
+        state.low_link.insert(node, state.next_index);
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        for &successor in adjacency.get(&node).into_iter().flatten() {
+            if !state.index.contains_key(&successor) {
+                strong_connect(successor, adjacency, state);
+                let successor_low = state.low_link[&successor];
+                let node_low = state.low_link[&node];
+                state.low_link.insert(node, node_low.min(successor_low));
+            } else if state.on_stack.contains(&successor) {
+                let successor_index = state.index[&successor];
+                let node_low = state.low_link[&node];
+                state.low_link.insert(node, node_low.min(successor_index));
+            }
+        }
+
+        if state.low_link[&node] == state.index[&node] {
+            let mut component = Vec::new();
+            loop {
+                let member = state.stack.pop().unwrap();
+                state.on_stack.remove(&member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.sccs.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: std::collections::HashMap::new(),
+        low_link: std::collections::HashMap::new(),
+        on_stack: std::collections::HashSet::new(),
+        stack: Vec::new(),
+        next_index: 0,
+        sccs: Vec::new(),
+    };
+
+    for &node in adjacency.keys() {
+        if !state.index.contains_key(&node) {
+            strong_connect(node, adjacency, &mut state);
+        }
+    }
+
+    state.sccs
+}
+
+/// Reconstructs one representative cycle within a strongly-connected
+/// component by DFS-ing from an arbitrary member and recording the back edge
+/// that closes the loop.
+fn reconstruct_cycle<T: Eq + std::hash::Hash + Copy>(
+    adjacency: &std::collections::HashMap<T, Vec<T>>,
+    component: &[T],
+) -> Option<Vec<T>> {
+    let in_component: std::collections::HashSet<T> = component.iter().copied().collect();
+    let start = *component.first()?;
+
+    let mut path = vec![start];
+    let mut visited: std::collections::HashSet<T> = std::iter::once(start).collect();
+
+    fn dfs<T: Eq + std::hash::Hash + Copy>(
+        node: T,
+        adjacency: &std::collections::HashMap<T, Vec<T>>,
+        in_component: &std::collections::HashSet<T>,
+        path: &mut Vec<T>,
+        visited: &mut std::collections::HashSet<T>,
+    ) -> Option<usize> {
+        for &successor in adjacency.get(&node).into_iter().flatten() {
+            if !in_component.contains(&successor) {
+                continue;
+            }
+            if let Some(cycle_start) = path.iter().position(|&n| n == successor) {
+                return Some(cycle_start);
+            }
+            if !visited.contains(&successor) {
+                visited.insert(successor);
+                path.push(successor);
+                if let Some(cycle_start) = dfs(successor, adjacency, in_component, path, visited) {
+                    return Some(cycle_start);
+                }
+                path.pop();
+            }
+        }
+        None
+    }
+
+    let cycle_start = dfs(start, adjacency, &in_component, &mut path, &mut visited)?;
+
+    let mut nodes: Vec<T> = path[cycle_start..].to_vec();
+    nodes.push(nodes[0]);
+    Some(nodes)
+}
+
+/// Groups `nodes` into topological generations ("waves") via Kahn's algorithm
+/// with level tracking: wave 0 is every node with no in-scope outgoing edge,
+/// wave N+1 is whatever becomes zero-in-degree once wave N's edges are
+/// removed. `edges` are `(dependent, dependency)` pairs; edges to nodes
+/// outside `nodes` are ignored. Generic over the node type so it can be
+/// unit-tested with plain values instead of requiring a real `PackageGraph`.
+fn compute_waves<T: Eq + std::hash::Hash + Copy>(
+    nodes: impl IntoIterator<Item = T>,
+    edges: impl IntoIterator<Item = (T, T)>,
+) -> Vec<Vec<T>> {
+    let in_scope: std::collections::HashSet<T> = nodes.into_iter().collect();
+    let mut in_degree: std::collections::HashMap<T, usize> =
+        in_scope.iter().map(|&node| (node, 0)).collect();
+    let mut successors: std::collections::HashMap<T, Vec<T>> =
+        in_scope.iter().map(|&node| (node, Vec::new())).collect();
+
+    for (dependent, dependency) in edges {
+        if in_scope.contains(&dependent) && in_scope.contains(&dependency) {
+            successors.get_mut(&dependency).unwrap().push(dependent);
+            *in_degree.get_mut(&dependent).unwrap() += 1;
+        }
+    }
+
+    let mut waves = Vec::new();
+    let mut wave: Vec<T> = in_degree
+        .iter()
+        .filter(|(_, &degree)| degree == 0)
+        .map(|(&node, _)| node)
+        .collect();
+
+    while !wave.is_empty() {
+        let mut next_wave = Vec::new();
+        for &node in &wave {
+            for &successor in successors.get(&node).into_iter().flatten() {
+                let degree = in_degree.get_mut(&successor).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    next_wave.push(successor);
+                }
+            }
+        }
+        waves.push(wave);
+        wave = next_wave;
+    }
+
+    waves
+}
+
+/// Groups the in-scope packages of `dependency_set` into parallel build waves
+/// using [`compute_waves`]. With `exec`, each package name is substituted for
+/// `{}` and run as a shell command within its wave.
+fn show_waves(
+    package_graph: &PackageGraph,
+    dependency_set: &PackageSet,
+    workspace_ids: &HashSet<&guppy::PackageId>,
+    all: bool,
+    exec: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use guppy::PackageId;
+
+    let nodes: Vec<&PackageId> = dependency_set
+        .packages(DependencyDirection::Forward)
+        .filter(|package| all || workspace_ids.contains(&package.id()))
+        .map(|package| package.id())
+        .collect();
+    let node_set: HashSet<&PackageId> = nodes.iter().copied().collect();
+
+    let mut edges = Vec::new();
+    for package in dependency_set.packages(DependencyDirection::Forward) {
+        if !node_set.contains(package.id()) {
+            continue;
+        }
+        for link in package.direct_links().filter(|link| !link.dev_only()) {
+            let dep_id = link.to().id();
+            if node_set.contains(dep_id) {
+                edges.push((package.id(), dep_id));
+            }
+        }
+    }
+
+    let mut waves = compute_waves(nodes, edges);
+    for wave in &mut waves {
+        wave.sort_by_key(|id| package_graph.metadata(id).unwrap().name());
+    }
+
+    for (wave_number, wave) in waves.iter().enumerate() {
+        let names: Vec<&str> = wave
+            .iter()
+            .map(|id| package_graph.metadata(id).unwrap().name())
+            .collect();
+        println!("Wave {}: {}", wave_number, names.join(", "));
+
+        if let Some(command_template) = exec {
+            // Spawn every command in the wave before waiting on any of them, so
+            // same-wave packages (which have no ordering constraint between them)
+            // actually build in parallel instead of one at a time.
+            let mut children = Vec::new();
+            for name in &names {
+                let command = command_template.replace("{}", name);
+                let child = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .spawn()?;
+                children.push((name, command, child));
+            }
+
+            for (name, command, mut child) in children {
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(format!("command failed for '{}': {}", name, command).into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Narrows `dependency_set` to only the packages and links that are actually
+/// enabled for the requested `--target` platform and `--features` selection,
+/// using guppy's feature graph (`FeatureSet`) and Cargo resolver
+/// (`CargoOptions`/`CargoSet`, which know how to evaluate `PlatformStatus` for
+/// `cfg(...)`-gated and feature-gated dependencies). A no-op when none of
+/// `--target`, `--features`, `--no-default-features`, or `--all-features` was passed.
+///
+/// `query` must be the same root query (workspace, or the `--package` root) that
+/// `dependency_set` was resolved from — the feature query is rebuilt over those same
+/// roots, not over every package already in `dependency_set`, since the latter has
+/// already expanded through platform-gated links and would make every one of them an
+/// always-included root, defeating `--target`/`--features` filtering entirely.
+fn narrow_by_platform_and_features<'g>(
+    package_graph: &'g PackageGraph,
+    dependency_set: PackageSet<'g>,
+    query: &PackageQuery<'g>,
+    args: &TopoArgs,
+) -> Result<PackageSet<'g>, Box<dyn std::error::Error>> {
+    use guppy::graph::cargo::{CargoOptions, CargoResolverVersion, CargoSet};
+    use guppy::graph::feature::{named_feature_filter, StandardFeatures};
+    use guppy::platform::{Platform, TargetFeatures};
+
+    if args.target.is_none()
+        && args.features.is_empty()
+        && !args.no_default_features
+        && !args.all_features
+    {
+        return Ok(dependency_set);
+    }
+
+    let mut cargo_opts = CargoOptions::new();
+    cargo_opts.set_resolver(CargoResolverVersion::V2);
+    if let Some(target) = &args.target {
+        let platform = Platform::new(target.clone(), TargetFeatures::Unknown)?;
+        cargo_opts.set_target_platform(platform);
+    }
+
+    let standard_features = if args.all_features {
+        StandardFeatures::All
+    } else if args.no_default_features {
+        StandardFeatures::None
+    } else {
+        StandardFeatures::Default
+    };
+    let feature_filter =
+        named_feature_filter(standard_features, args.features.iter().map(String::as_str));
+
+    let feature_graph = package_graph.feature_graph();
+    // Build `initials` from the query's seed feature ids directly (`resolve_ids`), not by
+    // fully resolving the feature query first: `FeatureQuery::resolve` walks every reachable
+    // feature with no platform awareness, so the resulting `FeatureSet` would already contain
+    // platform-gated packages like `winreg` under `cfg(windows)`. `CargoSet::new` treats every
+    // package in `initials` as a root, so passing that over-resolved set back in would make
+    // those platform-gated packages roots too, short-circuiting the platform filtering we're
+    // trying to apply below.
+    let feature_query = query.to_feature_query(feature_filter);
+    let seed_ids: Vec<_> = feature_query.initials().map(|m| m.feature_id()).collect();
+    let initials = feature_graph.resolve_ids(seed_ids)?;
+
+    let cargo_set = CargoSet::new(initials, feature_graph.resolve_none(), &cargo_opts)?;
+
+    Ok(dependency_set.intersection(&cargo_set.target_features().to_package_set()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -321,4 +974,256 @@ mod tests {
             assert!(package_graph.metadata(id).is_ok());
         }
     }
+
+    #[test]
+    fn test_package_json_schema() {
+        let output = TopoJson {
+            order: "forward",
+            packages: vec![PackageJson {
+                name: "foo".to_string(),
+                version: "0.1.0".to_string(),
+                is_workspace_member: true,
+                dependencies: vec!["bar".to_string()],
+            }],
+        };
+
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["order"], "forward");
+        assert_eq!(json["packages"][0]["name"], "foo");
+        assert_eq!(json["packages"][0]["is_workspace_member"], true);
+        assert_eq!(json["packages"][0]["dependencies"][0], "bar");
+    }
+
+    /// Builds a `TopoArgs` with every field at its default/empty value, so
+    /// individual tests only need to override the flags they care about.
+    fn default_topo_args() -> TopoArgs {
+        TopoArgs {
+            manifest_path: None,
+            reverse: false,
+            include_dev: false,
+            all: false,
+            compact: false,
+            format: None,
+            package: None,
+            dependents: false,
+            exclude: Vec::new(),
+            deny_cycles: false,
+            waves: false,
+            exec: None,
+            target: None,
+            features: Vec::new(),
+            no_default_features: false,
+            all_features: false,
+        }
+    }
+
+    #[test]
+    fn test_narrow_by_platform_and_features_noop_without_flags() {
+        let metadata = guppy::MetadataCommand::new();
+        let package_graph = metadata.build_graph().unwrap();
+        let query = package_graph.query_workspace();
+        let dependency_set = query.clone().resolve();
+        let before: Vec<_> = dependency_set
+            .package_ids(DependencyDirection::Forward)
+            .collect();
+
+        let args = default_topo_args();
+        let narrowed =
+            narrow_by_platform_and_features(&package_graph, dependency_set, &query, &args)
+                .unwrap();
+        let after: Vec<_> = narrowed.package_ids(DependencyDirection::Forward).collect();
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_narrow_by_platform_and_features_no_default_features() {
+        let metadata = guppy::MetadataCommand::new();
+        let package_graph = metadata.build_graph().unwrap();
+        let query = package_graph.query_workspace();
+        let dependency_set = query.clone().resolve();
+
+        let mut args = default_topo_args();
+        args.no_default_features = true;
+        let narrowed =
+            narrow_by_platform_and_features(&package_graph, dependency_set, &query, &args)
+                .expect("resolving with --no-default-features should exercise guppy's CargoSet path");
+
+        // Every workspace member is still its own root and must remain in scope.
+        let workspace_ids: HashSet<_> = package_graph
+            .resolve_workspace()
+            .package_ids(DependencyDirection::Forward)
+            .collect();
+        for id in workspace_ids {
+            assert!(narrowed.contains(id).unwrap_or(false));
+        }
+    }
+
+    #[test]
+    fn test_narrow_by_platform_and_features_target_excludes_other_platform_deps() {
+        // `clap`'s terminal-detection stack (`is-terminal`/`anstream`) pulls in
+        // `windows-sys` only under `cfg(windows)`. Narrowing to a Linux target must
+        // drop it from the resolved set, proving `--target` actually filters
+        // platform-gated dependencies instead of being a no-op.
+        let metadata = guppy::MetadataCommand::new();
+        let package_graph = metadata.build_graph().unwrap();
+        let query = package_graph.query_workspace();
+        let dependency_set = package_graph.query_workspace().resolve();
+
+        let mut args = default_topo_args();
+        args.target = Some("x86_64-unknown-linux-gnu".to_string());
+        let narrowed =
+            narrow_by_platform_and_features(&package_graph, dependency_set, &query, &args)
+                .expect("resolving a concrete --target should exercise guppy's CargoSet path");
+
+        let names: HashSet<_> = narrowed
+            .packages(DependencyDirection::Forward)
+            .map(|package| package.name().to_string())
+            .collect();
+
+        assert!(
+            !names.iter().any(|name| name == "windows-sys"),
+            "windows-sys should be filtered out when targeting x86_64-unknown-linux-gnu"
+        );
+    }
+
+    #[test]
+    fn test_mark_if_highlighted() {
+        assert_eq!(mark_if_highlighted("serde", Some("serde")), "⭐serde");
+        assert_eq!(mark_if_highlighted("serde", Some("clap")), "serde");
+        assert_eq!(mark_if_highlighted("serde", None), "serde");
+    }
+
+    #[test]
+    fn test_dependents_query_direction_finds_known_dependent() {
+        // `cargo-topo` itself depends on `clap`, so querying `clap`'s dependents
+        // in `Reverse` direction (what `--dependents` does) must surface
+        // `cargo-topo` as a transitive dependent.
+        let metadata = guppy::MetadataCommand::new();
+        let package_graph = metadata.build_graph().unwrap();
+
+        let root_pkg = package_graph
+            .packages()
+            .find(|pkg| pkg.name() == "clap")
+            .expect("clap is a direct dependency of cargo-topo");
+
+        let dependents_set = package_graph
+            .query_directed(std::iter::once(root_pkg.id()), DependencyDirection::Reverse)
+            .unwrap()
+            .resolve();
+
+        let names: HashSet<_> = dependents_set
+            .packages(DependencyDirection::Forward)
+            .map(|pkg| pkg.name().to_string())
+            .collect();
+
+        assert!(names.contains(env!("CARGO_PKG_NAME")));
+    }
+
+    #[test]
+    fn test_render_dot_body_nonempty() {
+        let metadata = guppy::MetadataCommand::new();
+        let package_graph = metadata.build_graph().unwrap();
+        let workspace_members = package_graph.resolve_workspace();
+        let workspace_ids: HashSet<_> = workspace_members
+            .package_ids(DependencyDirection::Forward)
+            .collect();
+        let dependency_set = package_graph.query_workspace().resolve();
+
+        let body = render_dot_body(
+            &package_graph,
+            &dependency_set,
+            &workspace_ids,
+            false,
+            false,
+        )
+        .unwrap();
+
+        assert!(!body.is_empty());
+    }
+
+    /// Asserts `cycle` is a valid closed trace over exactly `expected_nodes`
+    /// (same first/last element, every other element distinct and present).
+    fn assert_is_cycle_over(cycle: &[&str], expected_nodes: &[&str]) {
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(cycle.len(), expected_nodes.len() + 1);
+        let visited: HashSet<_> = cycle[..cycle.len() - 1].iter().collect();
+        assert_eq!(visited.len(), expected_nodes.len());
+        for node in expected_nodes {
+            assert!(visited.contains(node));
+        }
+    }
+
+    #[test]
+    fn test_tarjan_scc_two_node_cycle() {
+        let adjacency: std::collections::HashMap<&str, Vec<&str>> =
+            [("a", vec!["b"]), ("b", vec!["a"])].into_iter().collect();
+
+        let sccs = tarjan_scc(&adjacency);
+        assert_eq!(sccs.len(), 1);
+        let component = &sccs[0];
+        assert_eq!(component.len(), 2);
+
+        let cycle = reconstruct_cycle(&adjacency, component).unwrap();
+        assert_is_cycle_over(&cycle, &["a", "b"]);
+    }
+
+    #[test]
+    fn test_tarjan_scc_three_node_cycle() {
+        let adjacency: std::collections::HashMap<&str, Vec<&str>> =
+            [("a", vec!["b"]), ("b", vec!["c"]), ("c", vec!["a"])]
+                .into_iter()
+                .collect();
+
+        let sccs = tarjan_scc(&adjacency);
+        assert_eq!(sccs.len(), 1);
+        let component = &sccs[0];
+        assert_eq!(component.len(), 3);
+
+        let cycle = reconstruct_cycle(&adjacency, component).unwrap();
+        assert_is_cycle_over(&cycle, &["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_tarjan_scc_acyclic_graph_has_no_multi_node_components() {
+        let adjacency: std::collections::HashMap<&str, Vec<&str>> =
+            [("a", vec!["b"]), ("b", vec!["c"]), ("c", vec![])]
+                .into_iter()
+                .collect();
+
+        let sccs = tarjan_scc(&adjacency);
+        assert!(sccs.iter().all(|component| component.len() == 1));
+    }
+
+    #[test]
+    fn test_compute_waves_diamond() {
+        // top depends on (left, right), both of which depend on bottom.
+        let nodes = ["top", "left", "right", "bottom"];
+        let edges = [
+            ("top", "left"),
+            ("top", "right"),
+            ("left", "bottom"),
+            ("right", "bottom"),
+        ];
+
+        let mut waves = compute_waves(nodes, edges);
+        for wave in &mut waves {
+            wave.sort();
+        }
+
+        assert_eq!(
+            waves,
+            vec![vec!["bottom"], vec!["left", "right"], vec!["top"]]
+        );
+    }
+
+    #[test]
+    fn test_compute_waves_independent_nodes_share_wave_zero() {
+        let nodes = ["a", "b", "c"];
+        let waves = compute_waves(nodes, std::iter::empty());
+
+        assert_eq!(waves.len(), 1);
+        let wave0: HashSet<_> = waves[0].iter().collect();
+        assert_eq!(wave0, HashSet::from([&"a", &"b", &"c"]));
+    }
 }